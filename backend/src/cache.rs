@@ -0,0 +1,75 @@
+// Redis read-through cache for the GET endpoints.
+//
+// `handle_get_request` / `handle_get_all_request` check Redis before
+// touching Postgres, and the write handlers invalidate the keys they can
+// affect. Caching is entirely optional: when `REDIS_URL` isn't set (or
+// the server isn't reachable) every lookup is just a cache miss and the
+// handlers fall back to Postgres as before.
+
+use std::env;
+use redis::AsyncCommands;
+
+// how long a cached entry is considered fresh before it expires on its own
+const CACHE_TTL_SECONDS: u64 = 60;
+
+const ALL_USERS_KEY: &str = "users:all";
+
+pub struct Cache {
+    client: Option<redis::Client>,
+}
+
+impl Cache {
+    // Reads REDIS_URL from the environment; caching is disabled (every
+    // call below becomes a no-op) if it's unset or doesn't parse.
+    pub fn from_env() -> Self {
+        let client = env
+            ::var("REDIS_URL")
+            .ok()
+            .and_then(|url| redis::Client::open(url).ok());
+
+        Cache { client }
+    }
+
+    async fn connection(&self) -> Option<redis::aio::MultiplexedConnection> {
+        let client = self.client.as_ref()?;
+        client.get_multiplexed_async_connection().await.ok()
+    }
+
+    fn user_key(id: i32) -> String {
+        format!("user:{}", id)
+    }
+
+    pub async fn get_user(&self, id: i32) -> Option<String> {
+        let mut conn = self.connection().await?;
+        conn.get(Self::user_key(id)).await.ok()
+    }
+
+    pub async fn set_user(&self, id: i32, json: &str) {
+        if let Some(mut conn) = self.connection().await {
+            let _: Result<(), _> = conn.set_ex(Self::user_key(id), json, CACHE_TTL_SECONDS).await;
+        }
+    }
+
+    pub async fn invalidate_user(&self, id: i32) {
+        if let Some(mut conn) = self.connection().await {
+            let _: Result<(), _> = conn.del(Self::user_key(id)).await;
+        }
+    }
+
+    pub async fn get_all_users(&self) -> Option<String> {
+        let mut conn = self.connection().await?;
+        conn.get(ALL_USERS_KEY).await.ok()
+    }
+
+    pub async fn set_all_users(&self, json: &str) {
+        if let Some(mut conn) = self.connection().await {
+            let _: Result<(), _> = conn.set_ex(ALL_USERS_KEY, json, CACHE_TTL_SECONDS).await;
+        }
+    }
+
+    pub async fn invalidate_all_users(&self) {
+        if let Some(mut conn) = self.connection().await {
+            let _: Result<(), _> = conn.del(ALL_USERS_KEY).await;
+        }
+    }
+}
@@ -0,0 +1,85 @@
+// Runtime configuration.
+//
+// `DATABASE_URL` used to be baked into the binary at compile time via
+// `env!("DATABASE_URL")`, so the same build could never be pointed at a
+// different database. Settings are now read from the environment at
+// startup instead, optionally pre-loaded from a `.env.<RUST_ENV>` file
+// (`RUST_ENV` defaults to "development") so a deployment can still ship
+// its settings as a file without hard-coding them into the binary.
+
+use std::env;
+use std::fs;
+
+const DEFAULT_BIND_ADDR: &str = "0.0.0.0:8080";
+const DEFAULT_POOL_MAX_SIZE: usize = 10;
+const DEFAULT_CORS_ALLOWED_ORIGINS: &str = "*";
+
+pub struct Config {
+    pub database_url: String,
+    pub bind_addr: String,
+    pub pool_max_size: usize,
+    // the 200 OK header block, built once (with the CORS origin already
+    // baked in) so every handler doesn't have to re-format it on every
+    // response
+    pub ok_response_header: String,
+}
+
+impl Config {
+    // Loads settings from the environment, returning a clear error naming
+    // whichever required setting is missing rather than panicking deep
+    // inside a handler later.
+    pub fn load() -> Result<Self, String> {
+        let rust_env = env::var("RUST_ENV").unwrap_or_else(|_| "development".to_string());
+        load_dotenv_file(&format!(".env.{}", rust_env));
+
+        let database_url = require_env("DATABASE_URL")?;
+        let bind_addr = env::var("BIND_ADDR").unwrap_or_else(|_| DEFAULT_BIND_ADDR.to_string());
+        let pool_max_size = env
+            ::var("POOL_MAX_SIZE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_POOL_MAX_SIZE);
+        let cors_allowed_origins = env
+            ::var("CORS_ALLOWED_ORIGINS")
+            .unwrap_or_else(|_| DEFAULT_CORS_ALLOWED_ORIGINS.to_string());
+
+        let ok_response_header = format!(
+            "HTTP/1.1 200 OK\r\n
+    Content-Type: application/json\r\n
+    Access-Control-Allow-Origin: {}\r\n
+    Access-Control-Allow-Methods: GET, POST, PUT, DELETE\r\n
+    Access-Control-Allow-Headers: Content-Type\r\n\r\n",
+            cors_allowed_origins
+        );
+
+        Ok(Config { database_url, bind_addr, pool_max_size, ok_response_header })
+    }
+}
+
+fn require_env(key: &str) -> Result<String, String> {
+    env::var(key).map_err(|_| format!("missing required environment variable: {}", key))
+}
+
+// Minimal `.env`-style loader: `KEY=VALUE` lines, blank lines and `#`
+// comments ignored. A variable already set in the real environment wins
+// over the file, so a deployment can always override it.
+fn load_dotenv_file(path: &str) {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(_) => return,
+    };
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some((key, value)) = line.split_once('=') {
+            let key = key.trim();
+            if env::var(key).is_err() {
+                env::set_var(key, value.trim());
+            }
+        }
+    }
+}
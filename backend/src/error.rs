@@ -0,0 +1,53 @@
+// Typed error type for the HTTP layer.
+//
+// Every controller returns `Result<(String, String), ApiError>` instead of
+// panicking on a bad query or collapsing everything into a generic 500.
+// `into_response` turns whichever variant fired into the right status
+// line plus a JSON `{ "error": ... }` body.
+
+use crate::{ BAD_REQUEST, CONFLICT, INTERNAL_ERROR, NOT_FOUND, PAYLOAD_TOO_LARGE };
+
+#[derive(Debug)]
+pub enum ApiError {
+    NotFound,
+    BadRequest(String),
+    Db(tokio_postgres::Error),
+    Serde(serde_json::Error),
+    Conflict,
+    PayloadTooLarge,
+}
+
+impl ApiError {
+    pub fn into_response(self) -> (String, String) {
+        let (status_line, message) = match &self {
+            ApiError::NotFound => (NOT_FOUND, "User not found".to_string()),
+            ApiError::BadRequest(msg) => (BAD_REQUEST, msg.clone()),
+            ApiError::Conflict => (CONFLICT, "User already exists".to_string()),
+            ApiError::Db(e) => (INTERNAL_ERROR, e.to_string()),
+            ApiError::Serde(e) => (BAD_REQUEST, e.to_string()),
+            ApiError::PayloadTooLarge => (PAYLOAD_TOO_LARGE, "Request body too large".to_string()),
+        };
+
+        let body = serde_json::json!({ "error": message }).to_string();
+        (status_line.to_string(), body)
+    }
+}
+
+// A unique-constraint violation (SQLSTATE 23505) is a client error, not a server failure, so it gets its own variant
+// instead of falling into the generic `Db` / 500 bucket.
+impl From<tokio_postgres::Error> for ApiError {
+    fn from(e: tokio_postgres::Error) -> Self {
+        if let Some(db_error) = e.as_db_error() {
+            if db_error.code() == &tokio_postgres::error::SqlState::UNIQUE_VIOLATION {
+                return ApiError::Conflict;
+            }
+        }
+        ApiError::Db(e)
+    }
+}
+
+impl From<serde_json::Error> for ApiError {
+    fn from(e: serde_json::Error) -> Self {
+        ApiError::Serde(e)
+    }
+}
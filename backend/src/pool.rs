@@ -0,0 +1,107 @@
+// Connection pool for tokio-postgres clients.
+//
+// Opening a Client is a full TCP connection plus an auth handshake, so
+// doing that on every single request (like the old code did) is wasteful.
+// Instead we keep a small free-list of already-connected clients: handlers
+// check one out, use it, and it gets returned to the list automatically
+// when it goes out of scope.
+
+use std::sync::Arc;
+use tokio::sync::{ Mutex, OwnedSemaphorePermit, Semaphore };
+use tokio_postgres::{ Client, Error as PostgresError, NoTls };
+
+pub struct Pool {
+    db_url: String,
+    clients: Mutex<Vec<(Client, OwnedSemaphorePermit)>>,
+    // Caps the number of connections that exist at once, idle or checked
+    // out. `checkout` waits for a permit instead of dialing a new
+    // connection unconditionally, so the pool can't grow past `max_size`
+    // and blow through Postgres's own `max_connections`.
+    permits: Arc<Semaphore>,
+}
+
+impl Pool {
+    // Create an empty pool. Connections are opened lazily on first
+    // checkout rather than up front.
+    pub fn new(db_url: &str, max_size: usize) -> Self {
+        Pool {
+            db_url: db_url.to_string(),
+            clients: Mutex::new(Vec::new()),
+            permits: Arc::new(Semaphore::new(max_size)),
+        }
+    }
+
+    // Hand out a connection, reusing one from the free-list when possible.
+    // A connection the backend has already dropped is discarded instead of
+    // being handed back out, freeing its slot for a replacement. Once the
+    // free-list is empty this waits for a slot to free up before dialing a
+    // new connection, so at most `max_size` connections exist at once.
+    pub async fn checkout(self: &Arc<Self>) -> Result<PooledClient, PostgresError> {
+        let mut clients = self.clients.lock().await;
+        while let Some((client, permit)) = clients.pop() {
+            if !client.is_closed() {
+                return Ok(PooledClient { pool: self.clone(), client: Some(client), permit: Some(permit) });
+            }
+            // client is dead; dropping `permit` here frees its slot for the
+            // replacement connection below
+        }
+        drop(clients);
+
+        let permit = self.permits.clone().acquire_owned().await.expect("pool semaphore is never closed");
+
+        let (client, connection) = tokio_postgres::connect(&self.db_url, NoTls).await?;
+        // The connection object drives the actual socket I/O and has to be
+        // polled somewhere; spawn it onto its own task for the lifetime of
+        // the client so queries on `client` keep making progress.
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                eprintln!("connection error: {}", e);
+            }
+        });
+        Ok(PooledClient { pool: self.clone(), client: Some(client), permit: Some(permit) })
+    }
+
+    // Return a connection to the free-list, permit and all, so the slot it
+    // holds stays reserved for it rather than being released back to the
+    // semaphore while the connection is still open.
+    async fn checkin(&self, client: Client, permit: OwnedSemaphorePermit) {
+        let mut clients = self.clients.lock().await;
+        clients.push((client, permit));
+    }
+}
+
+// A `Client` checked out of a `Pool`. Derefs to the underlying client so it
+// can be used exactly like a plain `Client`, and returns itself to the pool
+// when dropped instead of being closed.
+pub struct PooledClient {
+    pool: Arc<Pool>,
+    client: Option<Client>,
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+impl std::ops::Deref for PooledClient {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        self.client.as_ref().unwrap()
+    }
+}
+
+impl std::ops::DerefMut for PooledClient {
+    fn deref_mut(&mut self) -> &mut Client {
+        self.client.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledClient {
+    fn drop(&mut self) {
+        // checkin is async (it awaits the mutex), but Drop can't await, so
+        // hand the client to a background task that puts it back.
+        if let (Some(client), Some(permit)) = (self.client.take(), self.permit.take()) {
+            let pool = self.pool.clone();
+            tokio::spawn(async move {
+                pool.checkin(client, permit).await;
+            });
+        }
+    }
+}
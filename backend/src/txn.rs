@@ -0,0 +1,69 @@
+// Transaction helper with automatic retry on serialization / deadlock failures.
+//
+// Postgres can abort a transaction with a retryable error that isn't a
+// real failure, just contention: a serialization failure (40001) or a
+// detected deadlock (40P01). `with_retry` runs the given closure inside a
+// fresh transaction and commits on success. If either the closure or the
+// commit itself fails with one of those two SQLSTATEs, it rolls back (if
+// not already rolled back by the failed commit) and tries again a few
+// times with a short backoff before giving up and surfacing the error.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+use tokio_postgres::error::SqlState;
+use tokio_postgres::{ Client, Transaction };
+
+use crate::error::ApiError;
+
+const MAX_ATTEMPTS: u32 = 3;
+const RETRY_BACKOFF: Duration = Duration::from_millis(50);
+
+pub type TxnFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, tokio_postgres::Error>> + Send + 'a>>;
+
+pub async fn with_retry<T, F>(client: &mut Client, mut f: F) -> Result<T, ApiError>
+    where F: for<'t> FnMut(&'t Transaction<'t>) -> TxnFuture<'t, T>
+{
+    let mut attempt = 0;
+
+    loop {
+        let txn = client.transaction().await?;
+
+        let result = match f(&txn).await {
+            Ok(value) =>
+                match txn.commit().await {
+                    Ok(()) => Ok(value),
+                    Err(e) => Err(e),
+                }
+            Err(e) => {
+                let _ = txn.rollback().await;
+                Err(e)
+            }
+        };
+
+        match result {
+            Ok(value) => {
+                return Ok(value);
+            }
+            Err(e) => {
+                attempt += 1;
+                if attempt < MAX_ATTEMPTS && is_retryable(&e) {
+                    tokio::time::sleep(RETRY_BACKOFF * attempt).await;
+                    continue;
+                }
+
+                return Err(e.into());
+            }
+        }
+    }
+}
+
+fn is_retryable(e: &tokio_postgres::Error) -> bool {
+    match e.as_db_error() {
+        Some(db_error) =>
+            db_error.code() == &SqlState::T_R_SERIALIZATION_FAILURE ||
+            db_error.code() == &SqlState::T_R_DEADLOCK_DETECTED,
+        None => false,
+    }
+}